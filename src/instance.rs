@@ -1,7 +1,8 @@
 use std::sync::Arc;
 use std::{
     borrow::Cow,
-    ffi::{c_char, CStr},
+    collections::HashSet,
+    ffi::{c_char, CStr, CString},
 };
 
 use ash::vk;
@@ -11,6 +12,26 @@ use winit::window::Window;
 const VALIDATION_LAYER_NAME: &CStr =
     unsafe { CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_KHRONOS_validation\0") };
 
+/// Returns the names of every instance layer the Vulkan loader currently reports.
+fn available_instance_layers(entry: &ash::Entry) -> Vec<CString> {
+    let properties = unsafe { entry.enumerate_instance_layer_properties() }
+        .expect("Failed to enumerate instance layer properties");
+    properties
+        .iter()
+        .map(|p| unsafe { CStr::from_ptr(p.layer_name.as_ptr()).to_owned() })
+        .collect()
+}
+
+/// Returns the names of every instance extension the Vulkan loader currently reports.
+fn available_instance_extensions(entry: &ash::Entry) -> Vec<CString> {
+    let properties = unsafe { entry.enumerate_instance_extension_properties(None) }
+        .expect("Failed to enumerate instance extension properties");
+    properties
+        .iter()
+        .map(|p| unsafe { CStr::from_ptr(p.extension_name.as_ptr()).to_owned() })
+        .collect()
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum VulkanApiVersion {
     V1_0,
@@ -19,6 +40,100 @@ pub enum VulkanApiVersion {
     V1_3,
 }
 
+/// Builder for [`InstanceForWindow`]. Use this instead of juggling `new`'s positional
+/// arguments when a caller also wants to configure things like suppressed VUIDs.
+pub struct InstanceBuilder {
+    window: Arc<Window>,
+    debug_strategy: VulkanDebugInfoStrategy,
+    vulkan_api_version: VulkanApiVersion,
+    suppressed_message_ids: HashSet<i32>,
+    application_name: Option<CString>,
+    application_version: u32,
+    engine_name: Option<CString>,
+    engine_version: u32,
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+}
+
+impl InstanceBuilder {
+    pub fn new(window: Arc<Window>) -> Self {
+        Self {
+            window,
+            debug_strategy: VulkanDebugInfoStrategy::DEFAULT_PANIC_ON_ERRORS,
+            vulkan_api_version: VulkanApiVersion::V1_1,
+            suppressed_message_ids: HashSet::new(),
+            application_name: None,
+            application_version: 0,
+            engine_name: None,
+            engine_version: 0,
+            message_severity: vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+            message_type: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        }
+    }
+
+    pub fn debug_strategy(mut self, debug_strategy: VulkanDebugInfoStrategy) -> Self {
+        self.debug_strategy = debug_strategy;
+        self
+    }
+
+    pub fn vulkan_api_version(mut self, vulkan_api_version: VulkanApiVersion) -> Self {
+        self.vulkan_api_version = vulkan_api_version;
+        self
+    }
+
+    /// Registers a validation message id (the VUID hash in `[... (<id>)]`) to silently
+    /// drop. Intended for known false positives a caller has already triaged, so a
+    /// `PanicOnErrorsPrintOthers` run doesn't hard-panic on them.
+    pub fn suppress_message_id(mut self, message_id: i32) -> Self {
+        self.suppressed_message_ids.insert(message_id);
+        self
+    }
+
+    pub fn application_name(mut self, name: &str) -> Self {
+        self.application_name =
+            Some(CString::new(name).expect("Application name must not contain a nul byte"));
+        self
+    }
+
+    pub fn application_version(mut self, version: u32) -> Self {
+        self.application_version = version;
+        self
+    }
+
+    pub fn engine_name(mut self, name: &str) -> Self {
+        self.engine_name =
+            Some(CString::new(name).expect("Engine name must not contain a nul byte"));
+        self
+    }
+
+    pub fn engine_version(mut self, version: u32) -> Self {
+        self.engine_version = version;
+        self
+    }
+
+    /// Overrides which severities the debug messenger is notified about. Defaults to
+    /// `ERROR | WARNING | INFO` (no `VERBOSE`).
+    pub fn message_severity(mut self, severity: vk::DebugUtilsMessageSeverityFlagsEXT) -> Self {
+        self.message_severity = severity;
+        self
+    }
+
+    /// Overrides which message types the debug messenger is notified about. Defaults to
+    /// `GENERAL | VALIDATION | PERFORMANCE`.
+    pub fn message_type(mut self, message_type: vk::DebugUtilsMessageTypeFlagsEXT) -> Self {
+        self.message_type = message_type;
+        self
+    }
+
+    pub fn build(self) -> InstanceForWindow {
+        InstanceForWindow::from_builder(self)
+    }
+}
+
 pub struct InstanceForWindow {
     handle: Arc<ash::Instance>,
     vk_api_version: VulkanApiVersion,
@@ -26,6 +141,10 @@ pub struct InstanceForWindow {
     entry: ash::Entry,
     #[allow(unused)]
     debug_worker: Option<(ash::ext::debug_utils::Instance, vk::DebugUtilsMessengerEXT)>,
+    #[allow(unused)]
+    debug_user_data: Option<Box<DebugUserData>>,
+    enabled_layers: Vec<CString>,
+    enabled_extensions: Vec<CString>,
 }
 
 impl InstanceForWindow {
@@ -34,7 +153,29 @@ impl InstanceForWindow {
         debug_strategy: VulkanDebugInfoStrategy,
         vulkan_api_version: VulkanApiVersion,
     ) -> Self {
-        let mut extensions_for_window = ash_window::enumerate_required_extensions(
+        InstanceBuilder::new(window)
+            .debug_strategy(debug_strategy)
+            .vulkan_api_version(vulkan_api_version)
+            .build()
+    }
+
+    fn from_builder(builder: InstanceBuilder) -> Self {
+        let InstanceBuilder {
+            window,
+            debug_strategy,
+            vulkan_api_version,
+            suppressed_message_ids,
+            application_name,
+            application_version,
+            engine_name,
+            engine_version,
+            message_severity,
+            message_type,
+        } = builder;
+
+        let entry = ash::Entry::linked();
+
+        let window_extensions = ash_window::enumerate_required_extensions(
             window
                 .display_handle()
                 .expect("Failed to get window handle")
@@ -43,73 +184,160 @@ impl InstanceForWindow {
         .expect("Failed to enumerate required vulkan extensions for window app")
         .to_vec();
 
-        extensions_for_window.push(vk::KHR_PORTABILITY_ENUMERATION_NAME.as_ptr());
-        extensions_for_window.push(vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME.as_ptr());
+        let available_layers = available_instance_layers(&entry);
+        let available_extensions = available_instance_extensions(&entry);
+
+        let validation_requested = !matches!(debug_strategy, VulkanDebugInfoStrategy::Idle);
+        let validation_layer_available = available_layers
+            .iter()
+            .any(|name| name.as_c_str() == VALIDATION_LAYER_NAME);
 
-        match debug_strategy {
-            VulkanDebugInfoStrategy::Idle => {}
-            _ => extensions_for_window.push(vk::EXT_DEBUG_UTILS_NAME.as_ptr()),
+        let is_extension_available = |name: &CStr| {
+            available_extensions
+                .iter()
+                .any(|ext| ext.as_c_str() == name)
+        };
+        let debug_utils_available = is_extension_available(vk::EXT_DEBUG_UTILS_NAME);
+
+        // Validation layers are a developer convenience, not a hard dependency: machines
+        // without the Vulkan SDK installed simply don't have them, so fall back to `Idle`
+        // instead of aborting `create_instance`. The debug messenger also needs
+        // `VK_EXT_debug_utils` itself, which is probed separately from the layer.
+        let debug_strategy = if validation_requested && !validation_layer_available {
+            eprintln!(
+                "Warning: requested Vulkan validation but {VALIDATION_LAYER_NAME:?} is not \
+                 available on this instance; continuing without validation"
+            );
+            VulkanDebugInfoStrategy::Idle
+        } else if validation_requested && !debug_utils_available {
+            eprintln!(
+                "Warning: requested Vulkan validation but {:?} is not available on this \
+                 instance; continuing without validation",
+                vk::EXT_DEBUG_UTILS_NAME
+            );
+            VulkanDebugInfoStrategy::Idle
+        } else {
+            debug_strategy
+        };
+
+        let mut optional_extensions: Vec<&CStr> = Vec::new();
+        if !matches!(debug_strategy, VulkanDebugInfoStrategy::Idle) {
+            optional_extensions.push(vk::EXT_DEBUG_UTILS_NAME);
         }
+        optional_extensions.push(vk::KHR_PORTABILITY_ENUMERATION_NAME);
+        optional_extensions.push(vk::KHR_GET_PHYSICAL_DEVICE_PROPERTIES2_NAME);
+        optional_extensions.retain(|name| is_extension_available(name));
 
-        let app_info = vk::ApplicationInfo::default().api_version(match vulkan_api_version {
-            VulkanApiVersion::V1_0 => vk::API_VERSION_1_0,
-            VulkanApiVersion::V1_1 => vk::API_VERSION_1_1,
-            VulkanApiVersion::V1_2 => vk::API_VERSION_1_2,
-            VulkanApiVersion::V1_3 => vk::API_VERSION_1_3,
-        });
+        let portability_enumeration_enabled =
+            optional_extensions.contains(&vk::KHR_PORTABILITY_ENUMERATION_NAME);
+
+        let mut extensions_for_window = window_extensions;
+        extensions_for_window.extend(optional_extensions.iter().map(|name| name.as_ptr()));
+
+        let enabled_extensions: Vec<CString> = extensions_for_window
+            .iter()
+            .map(|&ptr| unsafe { CStr::from_ptr(ptr).to_owned() })
+            .collect();
 
-        let enabled_layers: Vec<*const c_char> = match debug_strategy {
+        let mut app_info = vk::ApplicationInfo::default()
+            .application_version(application_version)
+            .engine_version(engine_version)
+            .api_version(match vulkan_api_version {
+                VulkanApiVersion::V1_0 => vk::API_VERSION_1_0,
+                VulkanApiVersion::V1_1 => vk::API_VERSION_1_1,
+                VulkanApiVersion::V1_2 => vk::API_VERSION_1_2,
+                VulkanApiVersion::V1_3 => vk::API_VERSION_1_3,
+            });
+        if let Some(application_name) = application_name.as_deref() {
+            app_info = app_info.application_name(application_name);
+        }
+        if let Some(engine_name) = engine_name.as_deref() {
+            app_info = app_info.engine_name(engine_name);
+        }
+
+        let enabled_layers: Vec<CString> = match debug_strategy {
             VulkanDebugInfoStrategy::Idle => vec![],
-            _ => vec![VALIDATION_LAYER_NAME.as_ptr()],
+            _ => vec![VALIDATION_LAYER_NAME.to_owned()],
+        };
+        let enabled_layer_ptrs: Vec<*const c_char> =
+            enabled_layers.iter().map(|name| name.as_ptr()).collect();
+
+        let mut instance_create_flags = vk::InstanceCreateFlags::default();
+        if portability_enumeration_enabled {
+            instance_create_flags |= vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR;
+        }
+
+        let debug_callback = match debug_strategy {
+            VulkanDebugInfoStrategy::Idle => None,
+            VulkanDebugInfoStrategy::PrintAll(p_fn)
+            | VulkanDebugInfoStrategy::PanicOnErrorsPrintOthers(p_fn) => Some(p_fn),
+            #[cfg(feature = "log")]
+            VulkanDebugInfoStrategy::Log(p_fn) => Some(p_fn),
         };
 
-        let instance_create_flags =
-            vk::InstanceCreateFlags::default() | vk::InstanceCreateFlags::ENUMERATE_PORTABILITY_KHR;
+        // Boxed so the callback can be handed a stable pointer to it (via `p_user_data`)
+        // that outlives instance creation; stored on `InstanceForWindow` below so it's
+        // freed once the messenger that references it is torn down.
+        let debug_user_data = debug_callback.map(|_| {
+            Box::new(DebugUserData {
+                suppressed_message_ids,
+                panic_on_errors: matches!(
+                    debug_strategy,
+                    VulkanDebugInfoStrategy::PanicOnErrorsPrintOthers(_)
+                ),
+            })
+        });
+        let user_data_ptr: *mut std::os::raw::c_void = debug_user_data
+            .as_deref()
+            .map(|data| data as *const DebugUserData as *mut std::os::raw::c_void)
+            .unwrap_or(std::ptr::null_mut());
 
-        let instance_create_info = vk::InstanceCreateInfo::default()
+        // Built up-front (rather than after `create_instance`) so it can be chained into
+        // `InstanceCreateInfo::push_next`, which lets the validation layers also catch
+        // problems in `vkCreateInstance`/`vkDestroyInstance` themselves. The very same
+        // create-info is reused below to stand up the persistent messenger once the
+        // instance exists, so the callback wiring only lives in one place.
+        let mut messenger_create_info = debug_callback.map(|p_fn| {
+            vk::DebugUtilsMessengerCreateInfoEXT::default()
+                .message_severity(message_severity)
+                .message_type(message_type)
+                .pfn_user_callback(p_fn)
+                .user_data(user_data_ptr)
+        });
+
+        let mut instance_create_info = vk::InstanceCreateInfo::default()
             .application_info(&app_info)
             .enabled_extension_names(&extensions_for_window)
-            .enabled_layer_names(&enabled_layers)
+            .enabled_layer_names(&enabled_layer_ptrs)
             .flags(instance_create_flags);
+        if let Some(messenger_create_info) = messenger_create_info.as_mut() {
+            instance_create_info = instance_create_info.push_next(messenger_create_info);
+        }
 
-        let entry = ash::Entry::linked();
         let instance = unsafe {
             entry
                 .create_instance(&instance_create_info, None)
                 .expect("Failed to create vulkan instance")
         };
 
-        let debug_worker = match debug_strategy {
-            VulkanDebugInfoStrategy::Idle => None,
-            VulkanDebugInfoStrategy::PrintAll(p_fn)
-            | VulkanDebugInfoStrategy::PanicOnErrorsPrintOthers(p_fn) => {
-                let debug_utils_loader = ash::ext::debug_utils::Instance::new(&entry, &instance);
-                let messenger_create_info = vk::DebugUtilsMessengerCreateInfoEXT::default()
-                    .message_severity(
-                        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-                            | vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
-                    )
-                    .message_type(
-                        vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-                            | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
-                            | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
-                    )
-                    .pfn_user_callback(p_fn);
-                let debug_messenger = unsafe {
-                    debug_utils_loader
-                        .create_debug_utils_messenger(&messenger_create_info, None)
-                        .expect("Failed to create debug messenger")
-                };
-                Some((debug_utils_loader, debug_messenger))
-            }
-        };
+        let debug_worker = messenger_create_info.map(|messenger_create_info| {
+            let debug_utils_loader = ash::ext::debug_utils::Instance::new(&entry, &instance);
+            let debug_messenger = unsafe {
+                debug_utils_loader
+                    .create_debug_utils_messenger(&messenger_create_info, None)
+                    .expect("Failed to create debug messenger")
+            };
+            (debug_utils_loader, debug_messenger)
+        });
 
         Self {
             handle: Arc::new(instance),
             vk_api_version: vulkan_api_version,
+            debug_user_data,
             entry,
             debug_worker,
+            enabled_layers,
+            enabled_extensions,
         }
     }
 
@@ -128,6 +356,18 @@ impl InstanceForWindow {
     pub fn handle(&self) -> Arc<ash::Instance> {
         self.handle.clone()
     }
+
+    /// Instance layers that were actually enabled, after filtering out anything the
+    /// Vulkan loader reported as unavailable.
+    pub fn enabled_layers(&self) -> &[CString] {
+        &self.enabled_layers
+    }
+
+    /// Instance extensions that were actually enabled, after filtering out anything the
+    /// Vulkan loader reported as unavailable.
+    pub fn enabled_extensions(&self) -> &[CString] {
+        &self.enabled_extensions
+    }
 }
 
 impl Drop for InstanceForWindow {
@@ -145,28 +385,54 @@ impl Drop for InstanceForWindow {
     }
 }
 
+/// State threaded through a debug messenger's `p_user_data`, so the callback can act on
+/// configuration supplied by the caller instead of only the fixed argument list Vulkan
+/// gives it.
+struct DebugUserData {
+    suppressed_message_ids: HashSet<i32>,
+    panic_on_errors: bool,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum VulkanDebugInfoStrategy {
     Idle,
     PrintAll(vk::PFN_vkDebugUtilsMessengerCallbackEXT),
     PanicOnErrorsPrintOthers(vk::PFN_vkDebugUtilsMessengerCallbackEXT),
+    #[cfg(feature = "log")]
+    Log(vk::PFN_vkDebugUtilsMessengerCallbackEXT),
 }
 
 impl VulkanDebugInfoStrategy {
     pub const DEFAULT_PRINT_ALL: Self = Self::PrintAll(Some(vulkan_debug_callback_print_all));
     pub const DEFAULT_PANIC_ON_ERRORS: Self =
         Self::PanicOnErrorsPrintOthers(Some(vulkan_debug_callback_panic_on_errors_print_others));
+    #[cfg(feature = "log")]
+    pub const DEFAULT_LOG: Self = Self::Log(Some(vulkan_debug_callback_log));
+}
+
+/// Reinterprets a messenger's `p_user_data` back into the `DebugUserData` that was passed
+/// to `DebugUtilsMessengerCreateInfoEXT::user_data` when the messenger was created.
+unsafe fn debug_user_data_from_ptr<'a>(
+    user_data: *mut std::os::raw::c_void,
+) -> Option<&'a DebugUserData> {
+    (user_data as *const DebugUserData).as_ref()
 }
 
 unsafe extern "system" fn vulkan_debug_callback_print_all(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     message_type: vk::DebugUtilsMessageTypeFlagsEXT,
     p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _user_data: *mut std::os::raw::c_void,
+    user_data: *mut std::os::raw::c_void,
 ) -> vk::Bool32 {
     let callback_data = *p_callback_data;
     let message_id_number = callback_data.message_id_number;
 
+    let debug_user_data = debug_user_data_from_ptr(user_data);
+    if debug_user_data.is_some_and(|data| data.suppressed_message_ids.contains(&message_id_number))
+    {
+        return vk::FALSE;
+    }
+
     let message_id_name = if callback_data.p_message_id_name.is_null() {
         Cow::from("")
     } else {
@@ -190,11 +456,17 @@ unsafe extern "system" fn vulkan_debug_callback_panic_on_errors_print_others(
     message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
     message_type: vk::DebugUtilsMessageTypeFlagsEXT,
     p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _user_data: *mut std::os::raw::c_void,
+    user_data: *mut std::os::raw::c_void,
 ) -> vk::Bool32 {
     let callback_data = *p_callback_data;
     let message_id_number = callback_data.message_id_number;
 
+    let debug_user_data = debug_user_data_from_ptr(user_data);
+    if debug_user_data.is_some_and(|data| data.suppressed_message_ids.contains(&message_id_number))
+    {
+        return vk::FALSE;
+    }
+
     let message_id_name = if callback_data.p_message_id_name.is_null() {
         Cow::from("")
     } else {
@@ -207,10 +479,21 @@ unsafe extern "system" fn vulkan_debug_callback_panic_on_errors_print_others(
         CStr::from_ptr(callback_data.p_message).to_string_lossy()
     };
 
-    if message_severity == vk::DebugUtilsMessageSeverityFlagsEXT::ERROR {
-        panic!(
-            "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
-        );
+    let panic_on_errors = debug_user_data
+        .map(|data| data.panic_on_errors)
+        .unwrap_or(true);
+    if message_severity == vk::DebugUtilsMessageSeverityFlagsEXT::ERROR && panic_on_errors {
+        // A callback fired while we're already unwinding (e.g. a destructor triggering a
+        // validation error during a panic) must not panic again, or the process aborts.
+        if std::thread::panicking() {
+            eprintln!(
+                "{message_severity:?} (panic suppressed, already unwinding):\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
+            );
+        } else {
+            panic!(
+                "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
+            );
+        }
     } else {
         println!(
             "{message_severity:?}:\n{message_type:?} [{message_id_name} ({message_id_number})] : {message}\n",
@@ -219,3 +502,48 @@ unsafe extern "system" fn vulkan_debug_callback_panic_on_errors_print_others(
 
     vk::FALSE
 }
+
+#[cfg(feature = "log")]
+unsafe extern "system" fn vulkan_debug_callback_log(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    user_data: *mut std::os::raw::c_void,
+) -> vk::Bool32 {
+    let callback_data = *p_callback_data;
+    let message_id_number = callback_data.message_id_number;
+
+    if debug_user_data_from_ptr(user_data)
+        .is_some_and(|data| data.suppressed_message_ids.contains(&message_id_number))
+    {
+        return vk::FALSE;
+    }
+
+    let message_id_name = if callback_data.p_message_id_name.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message_id_name).to_string_lossy()
+    };
+
+    let message = if callback_data.p_message.is_null() {
+        Cow::from("")
+    } else {
+        CStr::from_ptr(callback_data.p_message).to_string_lossy()
+    };
+
+    match message_severity {
+        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => {
+            log::error!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}")
+        }
+        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => {
+            log::warn!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}")
+        }
+        vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE => {
+            log::trace!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}")
+        }
+        // INFO and any future severities we don't special-case.
+        _ => log::debug!("{message_type:?} [{message_id_name} ({message_id_number})] : {message}"),
+    }
+
+    vk::FALSE
+}
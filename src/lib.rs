@@ -0,0 +1,2 @@
+pub mod instance;
+pub mod physical_device;
@@ -0,0 +1,160 @@
+use std::ffi::{CStr, CString};
+
+use ash::vk;
+
+use crate::instance::InstanceForWindow;
+
+/// Queue family indices resolved while evaluating a physical device.
+#[derive(Clone, Copy, Debug)]
+pub struct QueueFamilyIndices {
+    pub graphics: u32,
+    pub present: Option<u32>,
+}
+
+/// A physical device that satisfied every [`PhysicalDeviceSelector`] requirement, along
+/// with the state that was resolved while checking it so callers don't have to re-query it.
+pub struct SelectedPhysicalDevice {
+    pub handle: vk::PhysicalDevice,
+    pub queue_families: QueueFamilyIndices,
+    pub properties: vk::PhysicalDeviceProperties,
+    pub features: vk::PhysicalDeviceFeatures,
+}
+
+/// Picks a [`vk::PhysicalDevice`] out of `vkEnumeratePhysicalDevices`, scoring candidates
+/// by device type and rejecting any that are missing a required queue family or device
+/// extension.
+pub struct PhysicalDeviceSelector {
+    required_api_version: u32,
+    required_extensions: Vec<CString>,
+    prefer_discrete: bool,
+}
+
+impl Default for PhysicalDeviceSelector {
+    fn default() -> Self {
+        Self {
+            required_api_version: vk::API_VERSION_1_1,
+            required_extensions: Vec::new(),
+            prefer_discrete: true,
+        }
+    }
+}
+
+impl PhysicalDeviceSelector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn require_api_version(mut self, api_version: u32) -> Self {
+        self.required_api_version = api_version;
+        self
+    }
+
+    pub fn require_extension(mut self, extension_name: &CStr) -> Self {
+        self.required_extensions.push(extension_name.to_owned());
+        self
+    }
+
+    pub fn prefer_discrete(mut self, prefer_discrete: bool) -> Self {
+        self.prefer_discrete = prefer_discrete;
+        self
+    }
+
+    /// One-call path from a window instance to a ready-to-use physical device, mirroring
+    /// [`InstanceForWindow::with_window`]'s all-defaults convenience constructor.
+    pub fn with_instance(instance: &InstanceForWindow) -> Option<SelectedPhysicalDevice> {
+        Self::new().select(instance, None)
+    }
+
+    /// Scores every physical device the instance reports and returns the best match, or
+    /// `None` if nothing satisfies the requirements.
+    ///
+    /// Pass `surface` when the device also needs to present to a window; candidates
+    /// without a queue family that can present to it are rejected.
+    pub fn select(
+        &self,
+        instance: &InstanceForWindow,
+        surface: Option<(&ash::khr::surface::Instance, vk::SurfaceKHR)>,
+    ) -> Option<SelectedPhysicalDevice> {
+        let handle = instance.handle();
+
+        let candidates = unsafe { handle.enumerate_physical_devices() }
+            .expect("Failed to enumerate physical devices");
+
+        candidates
+            .into_iter()
+            .filter_map(|physical_device| self.evaluate(&handle, physical_device, surface))
+            .max_by_key(|candidate| self.score(candidate))
+    }
+
+    fn evaluate(
+        &self,
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        surface: Option<(&ash::khr::surface::Instance, vk::SurfaceKHR)>,
+    ) -> Option<SelectedPhysicalDevice> {
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        if properties.api_version < self.required_api_version {
+            return None;
+        }
+
+        let features = unsafe { instance.get_physical_device_features(physical_device) };
+
+        let queue_family_properties =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+
+        let graphics = queue_family_properties
+            .iter()
+            .enumerate()
+            .find_map(|(index, family)| {
+                family
+                    .queue_flags
+                    .contains(vk::QueueFlags::GRAPHICS)
+                    .then_some(index as u32)
+            })?;
+
+        let present = surface.and_then(|(surface_loader, surface_khr)| {
+            (0..queue_family_properties.len() as u32).find(|&index| unsafe {
+                surface_loader
+                    .get_physical_device_surface_support(physical_device, index, surface_khr)
+                    .unwrap_or(false)
+            })
+        });
+        if surface.is_some() && present.is_none() {
+            return None;
+        }
+
+        let available_extensions =
+            unsafe { instance.enumerate_device_extension_properties(physical_device) }
+                .expect("Failed to enumerate device extension properties");
+        let has_required_extensions = self.required_extensions.iter().all(|required| {
+            available_extensions.iter().any(|extension| {
+                let name = unsafe { CStr::from_ptr(extension.extension_name.as_ptr()) };
+                name == required.as_c_str()
+            })
+        });
+        if !has_required_extensions {
+            return None;
+        }
+
+        Some(SelectedPhysicalDevice {
+            handle: physical_device,
+            queue_families: QueueFamilyIndices { graphics, present },
+            properties,
+            features,
+        })
+    }
+
+    fn score(&self, candidate: &SelectedPhysicalDevice) -> u32 {
+        match candidate.properties.device_type {
+            vk::PhysicalDeviceType::DISCRETE_GPU => {
+                if self.prefer_discrete {
+                    2
+                } else {
+                    1
+                }
+            }
+            vk::PhysicalDeviceType::INTEGRATED_GPU => 1,
+            _ => 0,
+        }
+    }
+}